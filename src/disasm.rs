@@ -0,0 +1,166 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+//! Disassembly pass turning a decoded [`Module`] back into `.aluasm` source text.
+//!
+//! This is the counterpart to the binary object format's `Decode` impls: it does not
+//! interpret opcodes (that depends on the ISA extensions named by `Module::isae`), but it
+//! fully reconstructs everything the object format itself carries symbolically — exported
+//! routine labels, resolved external call targets, and the `.data`/`vars` segment.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use aluvm::data::{ByteStr, MaybeNumber};
+use aluvm::libs::LibId;
+
+use crate::model::{DataType, Module, Variable};
+
+/// Number of raw code bytes rendered per `.text` line when no symbol applies.
+const BYTES_PER_LINE: usize = 16;
+
+/// Disassembles a [`Module`] into `.aluasm`-flavoured source text.
+///
+/// Export offsets become routine labels, and every code offset recorded in
+/// [`Module::imports`]' call sites is annotated with the `(LibId, routine)` pair it resolves
+/// to, resolved via [`crate::model::CallTable::call_refs`]. Code that is not covered by a
+/// label or a call site is rendered as a raw hex byte dump, since turning it into ISA
+/// mnemonics requires the instruction set the module's `isae` segment names.
+pub fn disassemble(module: &Module) -> String {
+    let labels = labels_by_offset(module);
+    let call_sites = call_sites_by_offset(module);
+
+    let mut out = String::new();
+    writeln!(out, ".isae {}", module.isae).ok();
+    out.push('\n');
+
+    write_code_segment(&mut out, module, &labels, &call_sites);
+    out.push('\n');
+    write_data_segment(&mut out, module);
+
+    out
+}
+
+/// Inverts `Module::exports` into offset→routine-name lookup.
+fn labels_by_offset(module: &Module) -> BTreeMap<u16, &str> {
+    module.exports.iter().map(|(name, offset)| (*offset, name.as_str())).collect()
+}
+
+/// Flattens every `CallRef` in `Module::imports` into offset→`(LibId, routine)` lookup, so
+/// each code offset that performs an external call can be printed with its symbolic target
+/// instead of a raw `LibSite`.
+///
+/// `CallRef::sites` records the start of the patched operand — the same offset
+/// [`crate::linker::patch_call_site`] writes through — not the call instruction's own opcode
+/// byte, which immediately precedes it. The lookup is keyed by that opcode byte so the main
+/// scan below can recognise a call where it actually begins.
+fn call_sites_by_offset(module: &Module) -> BTreeMap<u16, (LibId, &str)> {
+    let mut sites = bmap! {};
+    for (lib, routine, offsets) in module.imports.call_refs() {
+        for offset in offsets {
+            sites.insert(offset.saturating_sub(1), (lib, routine));
+        }
+    }
+    sites
+}
+
+/// Byte length of a resolved external call instruction: the 1-byte opcode followed by the
+/// 2-byte little-endian routine offset and 1-byte `LibSeg` index that make up the 3-byte
+/// operand [`crate::linker::patch_call_site`] patches.
+const CALL_INSTR_LEN: usize = 4;
+
+fn write_code_segment(
+    out: &mut String,
+    module: &Module,
+    labels: &BTreeMap<u16, &str>,
+    call_sites: &BTreeMap<u16, (LibId, &str)>,
+) {
+    out.push_str(".text\n");
+
+    let mut pending = Vec::<u8>::new();
+    let mut line_start = 0usize;
+    let flush = |out: &mut String, pending: &mut Vec<u8>, line_start: usize| {
+        if pending.is_empty() {
+            return;
+        }
+        write!(out, "  ; @{:04x}  ", line_start).ok();
+        for byte in pending.iter() {
+            write!(out, "{:02x} ", byte).ok();
+        }
+        out.push('\n');
+        pending.clear();
+    };
+
+    let mut skip_until = 0usize;
+    for (pos, byte) in module.code.iter().enumerate() {
+        if pos < skip_until {
+            continue;
+        }
+        let offset = pos as u16;
+        if let Some(name) = labels.get(&offset) {
+            flush(out, &mut pending, line_start);
+            writeln!(out, "{}:", name).ok();
+            line_start = pos;
+        }
+        if let Some((lib, routine)) = call_sites.get(&offset) {
+            flush(out, &mut pending, line_start);
+            writeln!(out, "  call {}.{}", lib, routine).ok();
+            skip_until = pos + CALL_INSTR_LEN;
+            line_start = skip_until;
+            continue;
+        }
+        if pending.is_empty() {
+            line_start = pos;
+        }
+        pending.push(*byte);
+        if pending.len() == BYTES_PER_LINE {
+            flush(out, &mut pending, line_start);
+        }
+    }
+    flush(out, &mut pending, line_start);
+}
+
+fn write_data_segment(out: &mut String, module: &Module) {
+    out.push_str(".data\n");
+    for var in &module.vars {
+        writeln!(out, "  {}", format_variable(var)).ok();
+    }
+}
+
+fn format_variable(var: &Variable) -> String {
+    match &var.data {
+        DataType::ByteStr(bytes) => {
+            let rendered = match bytes {
+                None => String::from("\"\""),
+                Some(bytes) => match String::from_utf8(bytes.clone()) {
+                    Ok(s) => format!("{:?}", s),
+                    Err(_) => format!("0x{}", ByteStr::with(bytes)),
+                },
+            };
+            format!("bytes = {}  ; {}", rendered, var.info)
+        }
+        DataType::Int(layout, default) => {
+            format!(
+                "{}{} = {}  ; {}",
+                if layout.signed { "s" } else { "u" },
+                layout.bytes * 8,
+                format_maybe_number(default),
+                var.info
+            )
+        }
+        DataType::Float(layout, default) => {
+            format!("f:{} = {}  ; {}", layout, format_maybe_number(default), var.info)
+        }
+    }
+}
+
+fn format_maybe_number(number: &MaybeNumber) -> String {
+    match number.as_ref() {
+        None => String::from("none"),
+        Some(number) => format!("{}", number),
+    }
+}
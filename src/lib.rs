@@ -0,0 +1,15 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate amplify_derive;
+
+pub mod disasm;
+pub mod linker;
+pub mod model;
@@ -0,0 +1,145 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+//! Linker resolving external routine references between [`Module`]s into `aluvm` [`Lib`]s
+//! ready to be loaded into the VM.
+//!
+//! Assembling produces a [`Module`] per compilation unit, each carrying an unresolved
+//! [`CallTable`] of routines it imports by name and a map of routines it exports by name.
+//! Linking is the stage that binds the two together: for every import it finds the module
+//! exporting that routine, records the exporting library in the importing module's own
+//! [`LibSeg`] and patches the call sites recorded for it to reference that library's segment
+//! index and the resolved routine offset, then turns the result into a library the VM can
+//! run.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use aluvm::libs::{Lib, LibId, LibSeg};
+
+use crate::model::Module;
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LinkerError {
+    /// library `{0}` imported by the module being linked was not supplied to the linker
+    LibNotFound(LibId),
+
+    /// routine `{1}` is not found among the exports of library `{0}`
+    RoutineNotFound(LibId, String),
+
+    /// module imports routines from more distinct libraries than a single lib segment
+    /// (256 entries) can reference: failed to add library `{0}`
+    TooManyLibs(LibId),
+
+    /// call site at offset {2} referencing routine `{1}` imported from library `{0}` falls
+    /// outside the module's code segment
+    CallSiteOutOfRange(LibId, String, u16),
+
+    /// more than one module was registered under library id `{0}`; the later registration
+    /// would silently replace the first and any import resolved against that id would bind
+    /// to the wrong module's exports
+    DuplicateModule(LibId),
+
+    /// module linked under id `{0}` failed final assembly into a library
+    /// \n
+    /// details: {1}
+    LibAssembly(LibId, String),
+}
+
+/// Links a set of [`Module`]s together, resolving each one's imports against the others'
+/// exports and producing a library per module.
+///
+/// Export names are namespaced by the [`LibId`] a module is registered under, so the same
+/// routine name exported by two different modules is not a conflict — an import always binds
+/// against a specific `LibId`. The one way exports actually collide is registering two
+/// distinct modules under the *same* `LibId`, which [`Linker::link`] rejects.
+#[derive(Clone, Debug, Default)]
+pub struct Linker {
+    modules: BTreeMap<LibId, Module>,
+    duplicate_ids: BTreeSet<LibId>,
+}
+
+impl Linker {
+    pub fn new() -> Self { Linker::default() }
+
+    /// Adds a module to the link set under the given id, which is what other modules must
+    /// reference in their `CallTable` import entries to call into it.
+    pub fn with_module(mut self, id: LibId, module: Module) -> Self {
+        if self.modules.insert(id, module).is_some() {
+            self.duplicate_ids.insert(id);
+        }
+        self
+    }
+
+    /// Resolves every module's imports and assembles the whole set into libraries keyed by
+    /// the same ids they were added under.
+    pub fn link(mut self) -> Result<BTreeMap<LibId, Lib>, LinkerError> {
+        if let Some(id) = self.duplicate_ids.iter().next() {
+            return Err(LinkerError::DuplicateModule(*id));
+        }
+        self.resolve_imports()?;
+
+        let mut libs = bmap! {};
+        for (id, module) in self.modules {
+            let lib = Lib::with(&module.isae, module.code, module.data, module.libs)
+                .map_err(|err| LinkerError::LibAssembly(id, err.to_string()))?;
+            libs.insert(id, lib);
+        }
+        Ok(libs)
+    }
+
+    /// For every module's imports, finds the exporting module's routine offset, adds the
+    /// exporting library to the importing module's own [`LibSeg`] (assigning it a local
+    /// segment index), and patches each recorded call site with that index and offset.
+    fn resolve_imports(&mut self) -> Result<(), LinkerError> {
+        let exports: BTreeMap<LibId, BTreeMap<String, u16>> =
+            self.modules.iter().map(|(id, module)| (*id, module.exports.clone())).collect();
+
+        for (_id, module) in &mut self.modules {
+            for (lib, routine, sites) in module.imports.call_refs() {
+                let offset = *exports
+                    .get(&lib)
+                    .ok_or(LinkerError::LibNotFound(lib))?
+                    .get(routine)
+                    .ok_or_else(|| LinkerError::RoutineNotFound(lib, routine.to_owned()))?;
+
+                let lib_index = add_lib(&mut module.libs, lib)?;
+                for site in sites {
+                    patch_call_site(&mut module.code, lib, routine, *site, offset, lib_index)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds `lib` to `libs` if not already present and returns its segment index, the value a
+/// patched call site must reference to select this library at runtime.
+fn add_lib(libs: &mut LibSeg, lib: LibId) -> Result<u8, LinkerError> {
+    libs.add_lib(lib).map_err(|_| LinkerError::TooManyLibs(lib))?;
+    Ok(libs.index(lib).expect("library was just added to the segment"))
+}
+
+/// Overwrites the call site embedded at `site` in the module's code: the two-byte
+/// little-endian routine offset into the exporting library, followed by the one-byte index
+/// of that library within the module's own [`LibSeg`].
+fn patch_call_site(
+    code: &mut [u8],
+    lib: LibId,
+    routine: &str,
+    site: u16,
+    offset: u16,
+    lib_index: u8,
+) -> Result<(), LinkerError> {
+    let pos = site as usize;
+    let slot = code
+        .get_mut(pos..pos + 3)
+        .ok_or_else(|| LinkerError::CallSiteOutOfRange(lib, routine.to_owned(), site))?;
+    slot[..2].copy_from_slice(&offset.to_le_bytes());
+    slot[2] = lib_index;
+    Ok(())
+}
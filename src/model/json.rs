@@ -0,0 +1,241 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+//! Readable JSON mirrors of the object model, for tooling that would rather not parse the
+//! compact binary object format: editor integrations, build-system manifests, golden-file
+//! tests.
+//!
+//! Each type here has its own Cargo feature, all pulled in by the blanket `serde` feature:
+//! `serde_datatype` for [`DataType`], `serde_variable` for [`Variable`] (which embeds a
+//! `DataType` and so implies `serde_datatype`), `serde_calltable` for [`CallTable`], and
+//! `serde_module` for [`Module`] (which embeds both `Variable` and `CallTable`, and so
+//! implies both of those). A downstream crate with no use for JSON on, say, `CallTable`
+//! alone can disable just `serde_calltable` — but disabling it while `serde_module` stays on
+//! would leave `Module`'s own impl unbuildable, since a module's imports are a `CallTable`.
+//! The `#[cfg]` guards below enforce this dependency directly, so the crate still builds
+//! correctly even before a `Cargo.toml` encodes the same feature graph. None of this touches
+//! the canonical `Encode`/`Decode` path, which remains the on-chain/on-disk format.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use aluvm::data::{FloatLayout, IntLayout, MaybeNumber};
+use aluvm::libs::constants::{ISAE_SEGMENT_MAX_LEN, LIBS_SEGMENT_MAX_COUNT};
+use aluvm::libs::LibId;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::model::module::check_var_layout;
+use crate::model::{CallRef, CallTable, DataType, Module, ModuleError, Variable};
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if hex.len() % 2 != 0 {
+        return Err(String::from("hex string must have an even length"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|err| err.to_string()))
+        .collect()
+}
+
+#[cfg(feature = "serde_datatype")]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DataTypeJson {
+    Bytestr { default: Option<String> },
+    Int { signed: bool, bytes: u16, default: Option<String> },
+    Float { layout: u8, default: Option<String> },
+}
+
+#[cfg(feature = "serde_datatype")]
+impl From<&DataType> for DataTypeJson {
+    fn from(data: &DataType) -> Self {
+        match data {
+            DataType::ByteStr(bytes) => {
+                DataTypeJson::Bytestr { default: bytes.as_ref().map(|b| to_hex(b)) }
+            }
+            DataType::Int(layout, default) => DataTypeJson::Int {
+                signed: layout.signed,
+                bytes: layout.bytes,
+                default: default.as_ref().map(|n| n.to_string()),
+            },
+            DataType::Float(layout, default) => DataTypeJson::Float {
+                layout: *layout as u8,
+                default: default.as_ref().map(|n| n.to_string()),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde_datatype")]
+impl TryFrom<DataTypeJson> for DataType {
+    type Error = String;
+
+    fn try_from(json: DataTypeJson) -> Result<Self, Self::Error> {
+        Ok(match json {
+            DataTypeJson::Bytestr { default } => {
+                DataType::ByteStr(default.map(|hex| from_hex(&hex)).transpose()?)
+            }
+            DataTypeJson::Int { signed, bytes, default } => DataType::Int(
+                IntLayout { signed, bytes },
+                parse_default(default).map_err(|err| err.to_string())?,
+            ),
+            DataTypeJson::Float { layout, default } => DataType::Float(
+                FloatLayout::with(layout)
+                    .ok_or_else(|| format!("unknown float layout type {}", layout))?,
+                parse_default(default).map_err(|err| err.to_string())?,
+            ),
+        })
+    }
+}
+
+#[cfg(feature = "serde_datatype")]
+fn parse_default(default: Option<String>) -> Result<MaybeNumber, String> {
+    match default {
+        None => Ok(MaybeNumber::from(None)),
+        Some(literal) => {
+            MaybeNumber::from_str(&literal).map_err(|_| format!("invalid default value {}", literal))
+        }
+    }
+}
+
+#[cfg(feature = "serde_datatype")]
+impl Serialize for DataType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DataTypeJson::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde_datatype")]
+impl<'de> Deserialize<'de> for DataType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DataTypeJson::deserialize(deserializer)?.try_into().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(all(feature = "serde_variable", feature = "serde_datatype"))]
+#[derive(Serialize, Deserialize)]
+struct VariableJson {
+    info: String,
+    data: DataType,
+}
+
+#[cfg(all(feature = "serde_variable", feature = "serde_datatype"))]
+impl Serialize for Variable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VariableJson { info: self.info.clone(), data: self.data.clone() }.serialize(serializer)
+    }
+}
+
+#[cfg(all(feature = "serde_variable", feature = "serde_datatype"))]
+impl<'de> Deserialize<'de> for Variable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = VariableJson::deserialize(deserializer)?;
+        check_var_layout(&json.data, &json.info).map_err(D::Error::custom)?;
+        Ok(Variable { info: json.info, data: json.data })
+    }
+}
+
+#[cfg(feature = "serde_calltable")]
+#[derive(Serialize, Deserialize)]
+struct RoutineRefJson {
+    lib: String,
+    routine: String,
+    sites: std::collections::BTreeSet<u16>,
+}
+
+#[cfg(feature = "serde_calltable")]
+impl Serialize for CallTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let routines: Vec<RoutineRefJson> = self
+            .call_refs()
+            .map(|(lib, routine, sites)| RoutineRefJson {
+                lib: lib.to_string(),
+                routine: routine.to_owned(),
+                sites: sites.clone(),
+            })
+            .collect();
+        routines.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde_calltable")]
+impl<'de> Deserialize<'de> for CallTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let routines = Vec::<RoutineRefJson>::deserialize(deserializer)?;
+        let mut table = CallTable::default();
+        for entry in routines {
+            let lib = LibId::from_str(&entry.lib)
+                .map_err(|_| D::Error::custom(format!("invalid library id {}", entry.lib)))?;
+            table.insert_call_ref(lib, CallRef { routine: entry.routine, sites: entry.sites });
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(all(feature = "serde_module", feature = "serde_variable", feature = "serde_calltable", feature = "serde_datatype"))]
+#[derive(Serialize, Deserialize)]
+struct ModuleJson {
+    isae: String,
+    code: String,
+    data: String,
+    libs: Vec<String>,
+    imports: CallTable,
+    exports: BTreeMap<String, u16>,
+    vars: Vec<Variable>,
+}
+
+#[cfg(all(feature = "serde_module", feature = "serde_variable", feature = "serde_calltable", feature = "serde_datatype"))]
+impl Serialize for Module {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = ModuleJson {
+            isae: self.isae.clone(),
+            code: to_hex(&self.code),
+            data: to_hex(&self.data),
+            libs: self.libs.iter().map(|id| id.to_string()).collect(),
+            imports: self.imports.clone(),
+            exports: self.exports.clone(),
+            vars: self.vars.clone(),
+        };
+        json.serialize(serializer)
+    }
+}
+
+#[cfg(all(feature = "serde_module", feature = "serde_variable", feature = "serde_calltable", feature = "serde_datatype"))]
+impl<'de> Deserialize<'de> for Module {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = ModuleJson::deserialize(deserializer)?;
+        if json.isae.len() > ISAE_SEGMENT_MAX_LEN {
+            return Err(D::Error::custom(ModuleError::IsaeLengthLimExceeded(json.isae.len())));
+        }
+        if json.libs.len() > LIBS_SEGMENT_MAX_COUNT {
+            return Err(D::Error::custom(ModuleError::LibCountLimExceeded));
+        }
+        let lib_ids = json
+            .libs
+            .iter()
+            .map(|id| {
+                LibId::from_str(id).map_err(|_| D::Error::custom(format!("invalid library id {}", id)))
+            })
+            .collect::<Result<Vec<LibId>, _>>()?;
+        let libs =
+            aluvm::libs::LibSeg::from_iter(lib_ids).map_err(|err| D::Error::custom(err.to_string()))?;
+        Ok(Module {
+            isae: json.isae,
+            code: from_hex(&json.code).map_err(D::Error::custom)?,
+            data: from_hex(&json.data).map_err(D::Error::custom)?,
+            libs,
+            vars: json.vars,
+            imports: json.imports,
+            exports: json.exports,
+        })
+    }
+}
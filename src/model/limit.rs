@@ -0,0 +1,85 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+//! Byte budget guarding [`Module::decode_limited`] against over-large allocations declared
+//! by length-prefixed segments in untrusted input.
+//!
+//! Most of a [`Module`]'s fields decode through [`LimitedReader`], which charges every byte
+//! actually delivered by the inner reader against a [`DecodeLimit`] — not the size of the
+//! caller's buffer, since a short read (as `Read::read_exact` issues against a streaming
+//! reader) must only be charged for what it returned, or a legitimately-sized object would
+//! be charged several times over for the same bytes and spuriously exceed the budget.
+//!
+//! That per-byte charge alone cannot stop a length-prefixed segment from being allocated
+//! before a single byte of it is read — `vec![0u8; declared_len]` happens before the first
+//! `Read::read` call reaches the wrapper at all. For the two segments whose declared length
+//! drives such an allocation (`Module::code`, `Module::data`), [`Module::decode_limited`]
+//! reads the length prefix itself and checks it against the remaining budget *before*
+//! allocating, via [`DecodeLimit::claim`], instead of delegating to `ByteStr::decode`.
+//!
+//! [`Module`]: crate::model::Module
+//! [`Module::decode_limited`]: crate::model::Module::decode_limited
+
+use std::io::{self, Read};
+
+use crate::model::module::ModuleError;
+
+/// Maximum number of bytes a bounded decode may consume in total.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DecodeLimit {
+    /// No cap is enforced; behaves like an unbounded `Decode::decode`.
+    NoLimit,
+    /// At most this many bytes may be read across the whole decode.
+    Bounded(usize),
+}
+
+impl Default for DecodeLimit {
+    fn default() -> Self { DecodeLimit::NoLimit }
+}
+
+impl DecodeLimit {
+    pub fn bounded(max_bytes: usize) -> Self { DecodeLimit::Bounded(max_bytes) }
+
+    /// Claims `bytes` from the remaining budget, failing rather than letting the caller
+    /// read (and whatever allocation preceded that read) go through.
+    pub(crate) fn claim(&mut self, bytes: usize) -> Result<(), ModuleError> {
+        match self {
+            DecodeLimit::NoLimit => Ok(()),
+            DecodeLimit::Bounded(remaining) => match remaining.checked_sub(bytes) {
+                Some(left) => {
+                    *remaining = left;
+                    Ok(())
+                }
+                None => Err(ModuleError::DecodeLimitExceeded),
+            },
+        }
+    }
+}
+
+/// A [`Read`] wrapper that charges the bytes an inner read call actually delivers against a
+/// [`DecodeLimit`], so any `Decode` impl reading through it is bounded without needing to
+/// know about the limit itself. Borrows the limit rather than owning it so a caller can
+/// interleave explicit [`DecodeLimit::claim`] checks (e.g. before allocating a
+/// length-prefixed buffer) with reads that go through the wrapper.
+pub struct LimitedReader<'l, R> {
+    inner: R,
+    limit: &'l mut DecodeLimit,
+}
+
+impl<'l, R: Read> LimitedReader<'l, R> {
+    pub fn new(inner: R, limit: &'l mut DecodeLimit) -> Self { LimitedReader { inner, limit } }
+}
+
+impl<'l, R: Read> Read for LimitedReader<'l, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.limit
+            .claim(read)
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "module decode limit exceeded"))?;
+        Ok(read)
+    }
+}
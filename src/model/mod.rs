@@ -0,0 +1,14 @@
+// AluVM Assembler
+// To find more on AluVM please check <https://www.aluvm.org>
+//
+// Designed & written in 2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+// for Pandora Core AG
+
+mod limit;
+mod module;
+#[cfg(feature = "serde")]
+mod json;
+
+pub use limit::{DecodeLimit, LimitedReader};
+pub use module::{CallRef, CallTable, CallTableError, DataType, Module, ModuleError, Variable};
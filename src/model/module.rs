@@ -16,6 +16,8 @@ use aluvm::libs::constants::{ISAE_SEGMENT_MAX_LEN, LIBS_SEGMENT_MAX_COUNT};
 use aluvm::libs::{LibId, LibSeg, LibSegOverflow, LibSite};
 use amplify::IoError;
 
+use crate::model::limit::{DecodeLimit, LimitedReader};
+
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum CallTableError {
@@ -80,6 +82,18 @@ impl CallTable {
             .into_iter()
     }
 
+    /// Inserts a fully-formed call reference, overwriting any existing entry for the same
+    /// routine in that library. Used by alternative decoders (e.g. the `serde` JSON form)
+    /// that reconstruct a table from already-grouped `(LibId, CallRef)` pairs instead of
+    /// growing it one `find_or_insert` call at a time.
+    pub(crate) fn insert_call_ref(&mut self, lib: LibId, call_ref: CallRef) {
+        let routines = self.0.entry(lib).or_default();
+        match routines.iter_mut().find(|existing| existing.routine == call_ref.routine) {
+            Some(existing) => *existing = call_ref,
+            None => routines.push(call_ref),
+        }
+    }
+
     pub fn call_refs(&self) -> IntoIter<(LibId, &str, &BTreeSet<u16>)> {
         self.0
             .iter()
@@ -118,7 +132,7 @@ pub struct Module {
     pub exports: BTreeMap<String, u16>,
 }
 
-/// TODO: use in decoding (currently unused, had left after refactoring)
+/// Errors validating and decoding a [`Module`] from its binary object representation.
 #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum ModuleError {
@@ -129,6 +143,12 @@ pub enum ModuleError {
     #[from(io::Error)]
     Io(IoError),
 
+    /// malformed binary encoding of a module component
+    /// \n
+    /// details: {0}
+    #[from]
+    Decode(DecodeError),
+
     /// length of ISA extensions segment is {0} exceeds limit
     IsaeLengthLimExceeded(usize),
 
@@ -151,18 +171,15 @@ pub enum ModuleError {
     /// details: {0}
     ExternalNonUtf8(FromUtf8Error),
 
-    /// unknown type byte `{0}` for input variable having description "{1}"
-    VarUnknownType(u8, String),
-
-    /// wrong sign integer layout byte `{0}` for input variable having description "{1}"
-    VarWrongSignByte(u8, String),
-
     /// layout size ({layout_bytes} bytes) does not match {data_bytes} size of the default value
     /// for variable with description "{info}"
     VarWrongLayout { layout_bytes: u16, data_bytes: u16, info: String },
 
     /// unknown float layout type `{0}` for input variable having description "{1}"
     VarWrongFloatType(u8, String),
+
+    /// module exceeds the maximum number of bytes the decoder was configured to read
+    DecodeLimitExceeded,
 }
 
 impl Encode for DataType {
@@ -217,14 +234,53 @@ impl Encode for Variable {
 }
 
 impl Decode for Variable {
-    type Error = DecodeError;
+    type Error = ModuleError;
 
     fn decode(mut reader: impl Read) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
-        Ok(Variable { info: Decode::decode(&mut reader)?, data: Decode::decode(&mut reader)? })
+        let info = decode_utf8(&mut reader, ModuleError::VarNonUtf8)?;
+        let data = DataType::decode(&mut reader).map_err(|err| match err {
+            DecodeError::FloatLayout(byte) => ModuleError::VarWrongFloatType(byte, info.clone()),
+            err => ModuleError::from(err),
+        })?;
+        check_var_layout(&data, &info)?;
+        Ok(Variable { info, data })
+    }
+}
+
+/// Reads a `MaxLenByte`-prefixed string, reporting non-UTF8 content through `wrap` rather
+/// than a generic decode error, so the caller can attach which field it came from.
+fn decode_utf8(
+    mut reader: impl Read,
+    wrap: fn(FromUtf8Error) -> ModuleError,
+) -> Result<String, ModuleError> {
+    let bytes: Vec<u8> = MaxLenByte::decode(&mut reader)?.release();
+    String::from_utf8(bytes).map_err(wrap)
+}
+
+/// Checks that a decoded default value's byte length matches the layout declared for it,
+/// the validation [`ModuleError::VarWrongLayout`] exists for.
+pub(crate) fn check_var_layout(data: &DataType, info: &str) -> Result<(), ModuleError> {
+    let (layout_bytes, default) = match data {
+        DataType::ByteStr(_) => return Ok(()),
+        DataType::Int(layout, default) => (NumberLayout::bytes(layout), default),
+        DataType::Float(layout, default) => (NumberLayout::bytes(layout), default),
+    };
+    let default: Option<&Number> = default.as_ref();
+    let data_bytes = match default {
+        Some(number) => NumberLayout::bytes(number),
+        None => return Ok(()),
+    };
+    if data_bytes != layout_bytes {
+        return Err(ModuleError::VarWrongLayout {
+            layout_bytes,
+            data_bytes,
+            info: info.to_owned(),
+        });
     }
+    Ok(())
 }
 
 impl Encode for CallRef {
@@ -236,14 +292,14 @@ impl Encode for CallRef {
 }
 
 impl Decode for CallRef {
-    type Error = DecodeError;
+    type Error = ModuleError;
 
     fn decode(mut reader: impl Read) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
         Ok(CallRef {
-            routine: Decode::decode(&mut reader)?,
+            routine: decode_utf8(&mut reader, ModuleError::ExternalNonUtf8)?,
             sites: MaxLenWord::decode(&mut reader)?.release(),
         })
     }
@@ -264,7 +320,7 @@ impl Encode for CallTable {
 }
 
 impl Decode for CallTable {
-    type Error = DecodeError;
+    type Error = ModuleError;
 
     fn decode(mut reader: impl Read) -> Result<Self, Self::Error>
     where
@@ -273,7 +329,13 @@ impl Decode for CallTable {
         let len = u8::decode(&mut reader)?;
         let mut table = bmap! {};
         for _ in 0..len {
-            table.insert(LibId::decode(&mut reader)?, MaxLenWord::decode(&mut reader)?.release());
+            let lib = LibId::decode(&mut reader)?;
+            let routine_count = u16::decode(&mut reader)?;
+            let mut routines = Vec::new();
+            for _ in 0..routine_count {
+                routines.push(CallRef::decode(&mut reader)?);
+            }
+            table.insert(lib, routines);
         }
         Ok(CallTable(table))
     }
@@ -294,20 +356,87 @@ impl Encode for Module {
 }
 
 impl Decode for Module {
-    type Error = DecodeError;
+    type Error = ModuleError;
 
     fn decode(mut reader: impl Read) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
-        Ok(Module {
-            isae: Decode::decode(&mut reader)?,
-            code: ByteStr::decode(&mut reader)?.to_vec(),
-            data: ByteStr::decode(&mut reader)?.to_vec(),
-            libs: Decode::decode(&mut reader)?,
-            imports: Decode::decode(&mut reader)?,
-            exports: MaxLenWord::decode(&mut reader)?.release(),
-            vars: MaxLenWord::decode(&mut reader)?.release(),
-        })
+        // The u8-length prefix `String::decode` reads through already bounds `isae.len()` to
+        // `ISAE_SEGMENT_MAX_LEN` (0xFF), and `LibSeg::decode`'s u8-prefixed count to
+        // `LIBS_SEGMENT_MAX_COUNT`, so neither check below can trip on binary input; they
+        // state here the same invariant that the `serde` JSON form in [`crate::model::json`]
+        // checks on its own input, which does not go through this decoder.
+        let isae: String = Decode::decode(&mut reader)?;
+        if isae.len() > ISAE_SEGMENT_MAX_LEN {
+            return Err(ModuleError::IsaeLengthLimExceeded(isae.len()));
+        }
+
+        let code = ByteStr::decode(&mut reader)?.to_vec();
+        let data = ByteStr::decode(&mut reader)?.to_vec();
+
+        decode_module_tail(reader, isae, code, data)
+    }
+}
+
+/// Decodes everything past `Module::data`, shared by the plain [`Decode::decode`] path and
+/// [`Module::decode_limited`] once each has produced `isae`/`code`/`data` its own way.
+fn decode_module_tail(
+    mut reader: impl Read,
+    isae: String,
+    code: Vec<u8>,
+    data: Vec<u8>,
+) -> Result<Module, ModuleError> {
+    let libs = LibSeg::decode(&mut reader)?;
+    if libs.len() > LIBS_SEGMENT_MAX_COUNT {
+        return Err(ModuleError::LibCountLimExceeded);
+    }
+
+    let imports = CallTable::decode(&mut reader)?;
+
+    let exports_count = u16::decode(&mut reader)?;
+    let mut exports = bmap! {};
+    for _ in 0..exports_count {
+        let name = decode_utf8(&mut reader, ModuleError::RoutineNonUtf8)?;
+        exports.insert(name, u16::decode(&mut reader)?);
+    }
+
+    let vars_count = u16::decode(&mut reader)?;
+    let mut vars = Vec::new();
+    for _ in 0..vars_count {
+        vars.push(Variable::decode(&mut reader)?);
+    }
+
+    Ok(Module { isae, code, data, libs, vars, imports, exports })
+}
+
+/// Reads a `u16`-length-prefixed byte vector the same way `ByteStr::decode` does, except the
+/// declared length, together with its two-byte prefix, is charged against `limit` *before*
+/// the buffer for it is allocated, so a declared length far beyond the remaining budget is
+/// rejected instead of allocated.
+fn decode_bytes_limited(mut reader: impl Read, limit: &mut DecodeLimit) -> Result<Vec<u8>, ModuleError> {
+    let len = u16::decode(&mut reader)?;
+    limit.claim(2 + len as usize)?;
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+impl Module {
+    /// Decodes a module the same way [`Decode::decode`] does, but refuses to read more than
+    /// `limit` bytes in total. The `code` and `data` segments are read through
+    /// [`decode_bytes_limited`], which checks their declared length against `limit` before
+    /// allocating a buffer for them; every other field still flows through a
+    /// [`LimitedReader`] so the bytes it actually reads are charged against the same budget.
+    pub fn decode_limited(mut reader: impl Read, mut limit: DecodeLimit) -> Result<Self, ModuleError> {
+        let isae: String = Decode::decode(LimitedReader::new(&mut reader, &mut limit))?;
+        if isae.len() > ISAE_SEGMENT_MAX_LEN {
+            return Err(ModuleError::IsaeLengthLimExceeded(isae.len()));
+        }
+
+        let code = decode_bytes_limited(&mut reader, &mut limit)?;
+        let data = decode_bytes_limited(&mut reader, &mut limit)?;
+
+        decode_module_tail(LimitedReader::new(&mut reader, &mut limit), isae, code, data)
     }
 }